@@ -1,12 +1,14 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::{Debug, Display, Error as FmtError, Formatter},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use sp_consensus_aura::{digests::CompatibleDigestItem, sr25519::AuthoritySignature, Slot};
 use sp_runtime::SaturatedConversion;
 
 use crate::{
-    aleph_primitives::{AuraId, BlockNumber},
+    aleph_primitives::{AuraId, BlockNumber, MILLISECS_PER_BLOCK},
     session::{SessionBoundaryInfo, SessionId},
     session_map::AuthorityProvider,
     sync::{
@@ -23,6 +25,16 @@ pub enum CacheError {
     SessionTooOld(SessionId, SessionId),
     SessionInFuture(SessionId, SessionId),
     BadGenesisHeader,
+    /// The block's Aura pre-digest is missing or malformed.
+    MissingAuraPreDigest,
+    /// The cached Aura authority set for the block's session is empty, so no
+    /// author could have been expected for any slot.
+    EmptyAuraAuthorities(SessionId),
+    /// The block claims a slot further ahead of the current wall-clock slot than
+    /// the configured tolerance allows.
+    SlotTooFarInTheFuture { claimed: Slot, tolerated_until: Slot },
+    /// The block was not authored by the Aura authority expected for its claimed slot.
+    UnexpectedBlockAuthor { claimed_slot: Slot, author: AuraId },
 }
 
 impl Display for CacheError {
@@ -55,25 +67,108 @@ impl Display for CacheError {
                     "the provided genesis header does not match the cached genesis header"
                 )
             }
+            MissingAuraPreDigest => {
+                write!(f, "the block header is missing a valid Aura pre-digest")
+            }
+            EmptyAuraAuthorities(session) => {
+                write!(
+                    f,
+                    "the cached Aura authority set for session {session:?} is empty"
+                )
+            }
+            SlotTooFarInTheFuture {
+                claimed,
+                tolerated_until,
+            } => write!(
+                f,
+                "block claims slot {claimed:?}, which is further ahead than the tolerated {tolerated_until:?}"
+            ),
+            UnexpectedBlockAuthor {
+                claimed_slot,
+                author,
+            } => write!(
+                f,
+                "block for slot {claimed_slot:?} was not authored by the expected Aura authority, got {author:?}"
+            ),
         }
     }
 }
 
-struct CachedData {
+#[derive(Clone)]
+pub(crate) struct CachedData {
     session_verifier: SessionVerifier,
     aura_authorities: Vec<AuraId>,
 }
 
+/// A backend capable of persisting [`CachedData`] across node restarts, so that it
+/// doesn't need to be re-downloaded via [`AuthorityProvider`] every session.
+/// Implementations may retain a larger history on disk than the in-memory
+/// `cache_size`/`lower_bound` window [`VerifierCache`] keeps.
+pub trait CacheBackend<H> {
+    /// The genesis header the persisted data was recorded against, if anything has
+    /// been persisted yet.
+    fn genesis_header(&self) -> Option<H>;
+
+    /// Loads previously persisted data for `session_id`, if present.
+    fn load(&self, session_id: SessionId) -> Option<CachedData>;
+
+    /// Persists freshly downloaded data for `session_id`.
+    fn store(&mut self, session_id: SessionId, data: &CachedData);
+}
+
+/// A [`CacheBackend`] that persists nothing; every lookup falls back to
+/// [`download_data`]. This is the default backend, so existing [`VerifierCache`]
+/// users keep working without picking a concrete persistence layer.
+#[derive(Default)]
+pub struct NoBackend;
+
+impl<H> CacheBackend<H> for NoBackend {
+    fn genesis_header(&self) -> Option<H> {
+        None
+    }
+
+    fn load(&self, _session_id: SessionId) -> Option<CachedData> {
+        None
+    }
+
+    fn store(&mut self, _session_id: SessionId, _data: &CachedData) {}
+}
+
+/// Supplies the current Aura slot. Abstracts over `SystemTime::now()` so that
+/// [`VerifierCache::check_aura_header`] can be driven deterministically in tests.
+pub trait Clock {
+    fn current_slot(&self) -> Slot;
+}
+
+/// A [`Clock`] that derives the current slot from wall-clock time. This is the
+/// default clock, so existing [`VerifierCache`] users keep working without
+/// picking a concrete clock.
+#[derive(Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn current_slot(&self) -> Slot {
+        let now_millis: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .saturated_into();
+        Slot::from(now_millis / MILLISECS_PER_BLOCK)
+    }
+}
+
 /// Cache storing SessionVerifier structs and Aura authorities for multiple sessions.
 /// Keeps up to `cache_size` verifiers of top sessions.
 /// If the session is too new or ancient it will fail to return requested data.
 /// Highest session verifier this cache returns is for the session after the current finalization session.
 /// Lowest session verifier this cache returns is for `top_returned_session` - `cache_size`.
-pub struct VerifierCache<AP, FI, H>
+pub struct VerifierCache<AP, FI, H, B = NoBackend, C = SystemClock>
 where
     AP: AuthorityProvider,
     FI: FinalizationInfo,
     H: Header,
+    B: CacheBackend<H>,
+    C: Clock,
 {
     cached_data: HashMap<SessionId, CachedData>,
     session_info: SessionBoundaryInfo,
@@ -83,9 +178,14 @@ where
     /// Lowest currently available session.
     lower_bound: SessionId,
     genesis_header: H,
+    /// How many slots ahead of the current wall-clock slot a block is still
+    /// allowed to claim.
+    max_slots_ahead: u64,
+    backend: B,
+    clock: C,
 }
 
-impl<AP, FI, H> VerifierCache<AP, FI, H>
+impl<AP, FI, H> VerifierCache<AP, FI, H, NoBackend, SystemClock>
 where
     AP: AuthorityProvider,
     FI: FinalizationInfo,
@@ -97,6 +197,69 @@ where
         authority_provider: AP,
         cache_size: usize,
         genesis_header: H,
+        max_slots_ahead: u64,
+    ) -> Self {
+        Self::with_backend(
+            session_info,
+            finalization_info,
+            authority_provider,
+            cache_size,
+            genesis_header,
+            max_slots_ahead,
+            NoBackend,
+        )
+    }
+}
+
+impl<AP, FI, H, B> VerifierCache<AP, FI, H, B, SystemClock>
+where
+    AP: AuthorityProvider,
+    FI: FinalizationInfo,
+    H: Header,
+    B: CacheBackend<H>,
+{
+    pub fn with_backend(
+        session_info: SessionBoundaryInfo,
+        finalization_info: FI,
+        authority_provider: AP,
+        cache_size: usize,
+        genesis_header: H,
+        max_slots_ahead: u64,
+        backend: B,
+    ) -> Self {
+        Self::with_backend_and_clock(
+            session_info,
+            finalization_info,
+            authority_provider,
+            cache_size,
+            genesis_header,
+            max_slots_ahead,
+            backend,
+            SystemClock,
+        )
+    }
+}
+
+impl<AP, FI, H, B, C> VerifierCache<AP, FI, H, B, C>
+where
+    AP: AuthorityProvider,
+    FI: FinalizationInfo,
+    H: Header,
+    B: CacheBackend<H>,
+    C: Clock,
+{
+    /// Like [`VerifierCache::with_backend`], but also lets callers pick the
+    /// [`Clock`] used by [`check_aura_header`](VerifierCache::check_aura_header) —
+    /// tests use this to pin the current slot instead of reading wall-clock time.
+    pub fn with_backend_and_clock(
+        session_info: SessionBoundaryInfo,
+        finalization_info: FI,
+        authority_provider: AP,
+        cache_size: usize,
+        genesis_header: H,
+        max_slots_ahead: u64,
+        backend: B,
+        clock: C,
     ) -> Self {
         Self {
             cached_data: HashMap::new(),
@@ -106,6 +269,9 @@ where
             cache_size,
             lower_bound: SessionId(0),
             genesis_header,
+            max_slots_ahead,
+            backend,
+            clock,
         }
     }
 
@@ -114,6 +280,20 @@ where
     }
 }
 
+/// Extracts the claimed Aura slot from a block header's pre-digest.
+fn aura_pre_digest_slot<H: Header>(header: &H) -> Result<Slot, CacheError> {
+    header
+        .digest()
+        .logs()
+        .iter()
+        .find_map(|log| {
+            <sp_runtime::DigestItem as CompatibleDigestItem<AuthoritySignature>>::as_aura_pre_digest(
+                log,
+            )
+        })
+        .ok_or(CacheError::MissingAuraPreDigest)
+}
+
 fn download_data<AP: AuthorityProvider>(
     authority_provider: &AP,
     session_id: SessionId,
@@ -144,11 +324,13 @@ fn download_data<AP: AuthorityProvider>(
     })
 }
 
-impl<AP, FI, H> VerifierCache<AP, FI, H>
+impl<AP, FI, H, B, C> VerifierCache<AP, FI, H, B, C>
 where
     AP: AuthorityProvider,
     FI: FinalizationInfo,
-    H: Header,
+    H: Header + PartialEq,
+    B: CacheBackend<H>,
+    C: Clock,
 {
     // Prune old session data if necessary
     fn try_prune(&mut self, session_id: SessionId) {
@@ -192,11 +374,25 @@ where
 
         Ok(match self.cached_data.entry(session_id) {
             Entry::Occupied(occupied) => occupied.into_mut(),
-            Entry::Vacant(vacant) => vacant.insert(download_data(
-                &self.authority_provider,
-                session_id,
-                &self.session_info,
-            )?),
+            Entry::Vacant(vacant) => {
+                let data = match self.backend.load(session_id) {
+                    Some(data) => {
+                        if let Some(backend_genesis) = self.backend.genesis_header() {
+                            if backend_genesis != self.genesis_header {
+                                return Err(CacheError::BadGenesisHeader);
+                            }
+                        }
+                        data
+                    }
+                    None => {
+                        let data =
+                            download_data(&self.authority_provider, session_id, &self.session_info)?;
+                        self.backend.store(session_id, &data);
+                        data
+                    }
+                };
+                vacant.insert(data)
+            }
         })
     }
 
@@ -216,6 +412,44 @@ where
     pub fn get(&mut self, number: BlockNumber) -> Result<&SessionVerifier, CacheError> {
         Ok(&self.get_data(number)?.session_verifier)
     }
+
+    /// Checks that `header`'s claimed Aura slot is not further ahead of the current
+    /// wall-clock slot than `max_slots_ahead`, and that `author` is the Aura
+    /// authority expected to produce a block for that slot, using the authority set
+    /// cached for `parent_number`. Must be called using the number of the PARENT of
+    /// the verified block.
+    pub fn check_aura_header(
+        &mut self,
+        parent_number: BlockNumber,
+        header: &H,
+        author: &AuraId,
+    ) -> Result<(), CacheError> {
+        let claimed_slot = aura_pre_digest_slot(header)?;
+
+        let current_slot = self.clock.current_slot();
+        let tolerated_until = Slot::from(*current_slot + self.max_slots_ahead);
+        if claimed_slot > tolerated_until {
+            return Err(CacheError::SlotTooFarInTheFuture {
+                claimed: claimed_slot,
+                tolerated_until,
+            });
+        }
+
+        let session_id = self.session_info.session_id_from_block_num(parent_number);
+        let authorities = self.get_aura_authorities(parent_number)?;
+        if authorities.is_empty() {
+            return Err(CacheError::EmptyAuraAuthorities(session_id));
+        }
+        let expected_author = &authorities[*claimed_slot as usize % authorities.len()];
+        if expected_author != author {
+            return Err(CacheError::UnexpectedBlockAuthor {
+                claimed_slot,
+                author: author.clone(),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -223,10 +457,11 @@ mod tests {
     use std::{cell::Cell, collections::HashMap};
 
     use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-    use sp_runtime::testing::UintAuthorityId;
+    use sp_runtime::{testing::UintAuthorityId, DigestItem};
 
     use super::{
-        AuthorityProvider, BlockNumber, CacheError, FinalizationInfo, SessionVerifier,
+        AuthoritySignature, AuthorityProvider, BlockNumber, CacheBackend, CacheError, CachedData,
+        Clock, CompatibleDigestItem, FinalizationInfo, NoBackend, SessionVerifier, Slot,
         VerifierCache,
     };
     use crate::{
@@ -238,6 +473,7 @@ mod tests {
 
     const SESSION_PERIOD: u32 = 30;
     const CACHE_SIZE: usize = 3;
+    const MAX_SLOTS_AHEAD: u64 = 5;
 
     type TestVerifierCache<'a> =
         VerifierCache<MockAuthorityProvider, MockFinalizationInfo<'a>, MockHeader>;
@@ -325,6 +561,7 @@ mod tests {
             authority_provider,
             CACHE_SIZE,
             genesis_header,
+            MAX_SLOTS_AHEAD,
         )
     }
 
@@ -440,4 +677,238 @@ mod tests {
             Err(CacheError::UnknownAuthorities(SessionId(2)))
         );
     }
+
+    #[derive(Default)]
+    struct MockBackend {
+        data: HashMap<SessionId, CachedData>,
+        genesis_header: Option<MockHeader>,
+    }
+
+    impl MockBackend {
+        fn preload(&mut self, session_id: SessionId, data: CachedData) {
+            self.data.insert(session_id, data);
+        }
+    }
+
+    impl CacheBackend<MockHeader> for MockBackend {
+        fn genesis_header(&self) -> Option<MockHeader> {
+            self.genesis_header.clone()
+        }
+
+        fn load(&self, session_id: SessionId) -> Option<CachedData> {
+            self.data.get(&session_id).cloned()
+        }
+
+        fn store(&mut self, session_id: SessionId, data: &CachedData) {
+            self.data.insert(session_id, data.clone());
+        }
+    }
+
+    #[test]
+    fn serves_session_from_backend_without_authority_provider() {
+        let finalized_number = Cell::new(0);
+        let finalization_info = MockFinalizationInfo {
+            finalized_number: &finalized_number,
+        };
+        // The authority provider only ever knew about session 0.
+        let authority_provider = MockAuthorityProvider::new(0);
+        let genesis_header = MockHeader::random_parentless(0);
+
+        let mut backend = MockBackend {
+            genesis_header: Some(genesis_header.clone()),
+            ..Default::default()
+        };
+        backend.preload(
+            SessionId(1),
+            CachedData {
+                session_verifier: authority_data_for_session(1).into(),
+                aura_authorities: aura_authority_data_for_session(1),
+            },
+        );
+
+        let mut verifier = VerifierCache::with_backend(
+            SessionBoundaryInfo::new(SessionPeriod(SESSION_PERIOD)),
+            finalization_info,
+            authority_provider,
+            CACHE_SIZE,
+            genesis_header,
+            MAX_SLOTS_AHEAD,
+            backend,
+        );
+
+        finalize_first_in_session(&finalized_number, 1);
+
+        // Session 1 is only servable because the backend persisted it across the
+        // simulated restart; the authority provider alone doesn't have it.
+        let session_verifier = verifier
+            .get(2 * SESSION_PERIOD - 1)
+            .cloned()
+            .expect("should be served from the backend");
+        let expected_verifier: SessionVerifier = authority_data_for_session(1).into();
+        assert_eq!(session_verifier, expected_verifier);
+    }
+
+    #[test]
+    fn rejects_backend_data_with_mismatched_genesis_header() {
+        let finalized_number = Cell::new(0);
+        let finalization_info = MockFinalizationInfo {
+            finalized_number: &finalized_number,
+        };
+        let authority_provider = MockAuthorityProvider::new(0);
+        let genesis_header = MockHeader::random_parentless(0);
+        let other_genesis_header = MockHeader::random_parentless(0);
+
+        let mut backend = MockBackend {
+            genesis_header: Some(other_genesis_header),
+            ..Default::default()
+        };
+        backend.preload(
+            SessionId(0),
+            CachedData {
+                session_verifier: authority_data_for_session(0).into(),
+                aura_authorities: aura_authority_data_for_session(0),
+            },
+        );
+
+        let mut verifier = VerifierCache::with_backend(
+            SessionBoundaryInfo::new(SessionPeriod(SESSION_PERIOD)),
+            finalization_info,
+            authority_provider,
+            CACHE_SIZE,
+            genesis_header,
+            MAX_SLOTS_AHEAD,
+            backend,
+        );
+
+        assert_eq!(
+            verifier.get(SESSION_PERIOD - 1).cloned(),
+            Err(CacheError::BadGenesisHeader)
+        );
+    }
+
+    /// A [`Clock`] that always reports a fixed slot, so `check_aura_header` tests
+    /// can pin "now" instead of racing real wall-clock time.
+    struct FixedClock(Slot);
+
+    impl Clock for FixedClock {
+        fn current_slot(&self) -> Slot {
+            self.0
+        }
+    }
+
+    fn header_with_aura_slot(slot: Slot) -> MockHeader {
+        let mut header = MockHeader::random_parentless(0);
+        header
+            .digest_mut()
+            .push(<DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_pre_digest(slot));
+        header
+    }
+
+    fn setup_aura_header_test(
+        max_session_n: u32,
+        finalized_number: &'_ Cell<u32>,
+        current_slot: Slot,
+    ) -> VerifierCache<MockAuthorityProvider, MockFinalizationInfo<'_>, MockHeader, NoBackend, FixedClock>
+    {
+        let finalization_info = MockFinalizationInfo { finalized_number };
+        let authority_provider = MockAuthorityProvider::new(max_session_n);
+        let genesis_header = MockHeader::random_parentless(0);
+
+        VerifierCache::with_backend_and_clock(
+            SessionBoundaryInfo::new(SessionPeriod(SESSION_PERIOD)),
+            finalization_info,
+            authority_provider,
+            CACHE_SIZE,
+            genesis_header,
+            MAX_SLOTS_AHEAD,
+            NoBackend,
+            FixedClock(current_slot),
+        )
+    }
+
+    #[test]
+    fn accepts_an_in_tolerance_slot_from_the_expected_author() {
+        let finalized_number = Cell::new(0);
+        let current_slot = Slot::from(100);
+        let mut verifier = setup_aura_header_test(0, &finalized_number, current_slot);
+
+        let claimed_slot = Slot::from(*current_slot + MAX_SLOTS_AHEAD);
+        let header = header_with_aura_slot(claimed_slot);
+        let authorities = aura_authority_data_for_session(0);
+        let expected_author = &authorities[*claimed_slot as usize % authorities.len()];
+
+        assert_eq!(verifier.check_aura_header(0, &header, expected_author), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_slot_beyond_the_tolerated_window() {
+        let finalized_number = Cell::new(0);
+        let current_slot = Slot::from(100);
+        let mut verifier = setup_aura_header_test(0, &finalized_number, current_slot);
+
+        let tolerated_until = Slot::from(*current_slot + MAX_SLOTS_AHEAD);
+        let claimed_slot = Slot::from(*tolerated_until + 1);
+        let header = header_with_aura_slot(claimed_slot);
+        let authorities = aura_authority_data_for_session(0);
+        let author = &authorities[*claimed_slot as usize % authorities.len()];
+
+        assert_eq!(
+            verifier.check_aura_header(0, &header, author),
+            Err(CacheError::SlotTooFarInTheFuture {
+                claimed: claimed_slot,
+                tolerated_until,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_an_in_tolerance_slot_from_the_wrong_author() {
+        let finalized_number = Cell::new(0);
+        let current_slot = Slot::from(100);
+        let mut verifier = setup_aura_header_test(0, &finalized_number, current_slot);
+
+        let claimed_slot = Slot::from(*current_slot + MAX_SLOTS_AHEAD);
+        let header = header_with_aura_slot(claimed_slot);
+        let authorities = aura_authority_data_for_session(0);
+        let expected_author = authorities[*claimed_slot as usize % authorities.len()].clone();
+        let wrong_author = authorities
+            .iter()
+            .find(|id| **id != expected_author)
+            .expect("session has more than one authority")
+            .clone();
+
+        assert_eq!(
+            verifier.check_aura_header(0, &header, &wrong_author),
+            Err(CacheError::UnexpectedBlockAuthor {
+                claimed_slot,
+                author: wrong_author,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_a_header_when_the_cached_authority_set_is_empty() {
+        let finalized_number = Cell::new(0);
+        let current_slot = Slot::from(100);
+        let mut verifier = setup_aura_header_test(0, &finalized_number, current_slot);
+
+        // Poke an empty authority set into the cache directly; a real authority
+        // provider should never hand one out, but the cache must not panic if it did.
+        verifier.cached_data.insert(
+            SessionId(0),
+            CachedData {
+                session_verifier: authority_data_for_session(0).into(),
+                aura_authorities: Vec::new(),
+            },
+        );
+
+        let claimed_slot = Slot::from(*current_slot + MAX_SLOTS_AHEAD);
+        let header = header_with_aura_slot(claimed_slot);
+        let author = aura_authority_data_for_session(0)[0].clone();
+
+        assert_eq!(
+            verifier.check_aura_header(0, &header, &author),
+            Err(CacheError::EmptyAuraAuthorities(SessionId(0)))
+        );
+    }
 }
@@ -1,7 +1,12 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-pub use pallet::*;
+pub use pallet_pink_scorpion::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 #[frame_support::pallet]
 pub mod pallet_pink_scorpion {
@@ -9,6 +14,7 @@ pub mod pallet_pink_scorpion {
     use frame_support::pallet_prelude::*;
     use frame_system::pallet_prelude::*;
     use scale_info::prelude::vec::Vec;
+    use sp_io::hashing::blake2_256;
 
     #[pallet::pallet]
     pub struct Pallet<T>(_);
@@ -16,24 +22,126 @@ pub mod pallet_pink_scorpion {
     #[pallet::config]
     pub trait Config: frame_system::Config {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Maximum length, in bytes, of the `creationtime` field of an [`FSEvent`].
+        #[pallet::constant]
+        type MaxCreationTimeLen: Get<u32>;
+
+        /// Maximum length, in bytes, of the `filepath` field of an [`FSEvent`].
+        #[pallet::constant]
+        type MaxFilePathLen: Get<u32>;
+
+        /// Maximum length, in bytes, of the `eventkey` field of an [`FSEvent`].
+        #[pallet::constant]
+        type MaxEventKeyLen: Get<u32>;
+
+        /// Maximum number of [`FSEvent`]s retained per account.
+        #[pallet::constant]
+        type MaxHistory: Get<u32>;
+
+        /// Maximum number of chunks a single file's Merkle manifest may cover.
+        #[pallet::constant]
+        type MaxChunks: Get<u32>;
+
+        /// Maximum number of sibling hashes in a Merkle inclusion proof.
+        #[pallet::constant]
+        type MaxProofLen: Get<u32>;
+
+        /// Maximum length, in bytes, of an RSA-wrapped AES content key.
+        #[pallet::constant]
+        type MaxWrappedKeyLen: Get<u32>;
+
+        /// Maximum length, in bytes, of an AES IV/nonce.
+        #[pallet::constant]
+        type MaxIvLen: Get<u32>;
     }
 
     #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Debug, Clone, PartialEq, Eq)]
-    pub struct FSEvent {
-        pub creationtime: [u8; 64],
-        pub filepath: [u8; 256],
-        pub eventkey: [u8; 128],
+    #[scale_info(skip_type_params(T))]
+    pub struct FSEvent<T: Config> {
+        /// Position of this event in the account's history, starting at 0.
+        pub index: u32,
+        pub creationtime: BoundedVec<u8, T::MaxCreationTimeLen>,
+        pub filepath: BoundedVec<u8, T::MaxFilePathLen>,
+        pub eventkey: BoundedVec<u8, T::MaxEventKeyLen>,
     }
 
+    /// Commitment to a file's chunk layout: the Merkle root over its chunk hashes
+    /// and the number of chunks it was split into.
+    #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Manifest {
+        pub root: [u8; 32],
+        pub chunk_count: u32,
+    }
+
+    /// An AES content key, RSA-wrapped for a single recipient, plus the IV/nonce
+    /// needed to decrypt the file it unlocks. The chain only ever sees ciphertext.
+    #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Debug, Clone, PartialEq, Eq)]
+    #[scale_info(skip_type_params(T))]
+    pub struct WrappedKey<T: Config> {
+        pub wrapped_key: BoundedVec<u8, T::MaxWrappedKeyLen>,
+        pub iv: BoundedVec<u8, T::MaxIvLen>,
+    }
+
+    /// Proof material submitted to `reassembled` to show the caller holds chunks
+    /// that fold back to the manifest's stored root.
+    #[derive(Encode, Decode, TypeInfo, Debug, Clone, PartialEq, Eq)]
+    pub enum ReassemblyProof {
+        /// The full, recomputed list of per-chunk hashes, in order.
+        Leaves(Vec<[u8; 32]>),
+        /// A single chunk hash plus its Merkle inclusion proof: one `(is_left_sibling,
+        /// sibling_hash)` pair per level, from the leaf up to the root.
+        Inclusion {
+            chunk_hash: [u8; 32],
+            proof: Vec<(bool, [u8; 32])>,
+        },
+    }
+
+    /// Append-only, per-account log of disassembly/reassembly events. Bounded to
+    /// `T::MaxHistory` entries; once full, further events are rejected rather than
+    /// overwriting earlier history.
     #[pallet::storage]
-    #[pallet::getter(fn info)]
-    pub(super) type DisReAssembly<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, FSEvent, OptionQuery>;
+    #[pallet::getter(fn history)]
+    pub(super) type DisReAssembly<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<FSEvent<T>, T::MaxHistory>, ValueQuery>;
+
+    /// Chunk manifest for a file, keyed by its `eventkey`.
+    #[pallet::storage]
+    #[pallet::getter(fn manifest)]
+    pub(super) type Manifests<T: Config> =
+        StorageMap<_, Blake2_128Concat, BoundedVec<u8, T::MaxEventKeyLen>, Manifest, OptionQuery>;
+
+    /// The account that disassembled a file, and so is authorised to manage access
+    /// to it via `grant_access`/`revoke_access`.
+    #[pallet::storage]
+    #[pallet::getter(fn manifest_owner)]
+    pub(super) type ManifestOwners<T: Config> =
+        StorageMap<_, Blake2_128Concat, BoundedVec<u8, T::MaxEventKeyLen>, T::AccountId, OptionQuery>;
+
+    /// Access-control list: for a given `eventkey`, the AES content key wrapped for
+    /// each recipient authorised to reassemble the file.
+    #[pallet::storage]
+    #[pallet::getter(fn access_grant)]
+    pub(super) type AccessGrants<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        BoundedVec<u8, T::MaxEventKeyLen>,
+        Blake2_128Concat,
+        T::AccountId,
+        WrappedKey<T>,
+        OptionQuery,
+    >;
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        FileDisassembled { who: T::AccountId, event: FSEvent },
-        FileReassembled { who: T::AccountId, event: FSEvent },
+        FileDisassembled { who: T::AccountId, index: u32, event: FSEvent<T> },
+        FileReassembled { who: T::AccountId, index: u32, event: FSEvent<T> },
+        /// The chunks or proof submitted to `reassembled` did not fold to the
+        /// manifest's stored root.
+        ReassemblyMismatch { who: T::AccountId, event_key: BoundedVec<u8, T::MaxEventKeyLen> },
+        AccessGranted { event_key: BoundedVec<u8, T::MaxEventKeyLen>, recipient: T::AccountId },
+        AccessRevoked { event_key: BoundedVec<u8, T::MaxEventKeyLen>, recipient: T::AccountId },
     }
 
     #[pallet::error]
@@ -41,45 +149,80 @@ pub mod pallet_pink_scorpion {
         CreationTimeTooLong,
         FilePathTooLong,
         EventKeyTooLong,
+        /// The account's history already holds `MaxHistory` events.
+        HistoryFull,
+        /// `disassembled` was called with no chunk hashes to build a manifest from.
+        EmptyChunkList,
+        /// More chunk hashes were submitted than `MaxChunks` allows.
+        TooManyChunks,
+        /// More proof siblings were submitted than `MaxProofLen` allows.
+        ProofTooLong,
+        /// No manifest is stored for this `eventkey`.
+        UnknownManifest,
+        /// The number of submitted leaves does not match the manifest's `chunk_count`.
+        ChunkCountMismatch,
+        /// The submitted RSA-wrapped key exceeds `MaxWrappedKeyLen`.
+        WrappedKeyTooLong,
+        /// The submitted IV/nonce exceeds `MaxIvLen`.
+        IvTooLong,
+        /// Only the account that first disassembled a file at this `eventkey` may
+        /// re-disassemble it or grant/revoke access to it.
+        NotFileOwner,
+        /// No access grant exists for this `eventkey`/recipient pair.
+        UnknownGrant,
+        /// The caller has not been granted access to reassemble this file.
+        AccessDenied,
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(2))]
         pub fn disassembled(
             origin: OriginFor<T>,
             creation_time: Vec<u8>,
             file_path: Vec<u8>,
             event_key: Vec<u8>,
+            chunk_hashes: Vec<[u8; 32]>,
         ) -> DispatchResult {
             let sender = ensure_signed(origin)?;
 
-            ensure!(creation_time.len() <= 64, Error::<T>::CreationTimeTooLong);
-            ensure!(file_path.len() <= 256, Error::<T>::FilePathTooLong);
-            ensure!(event_key.len() <= 128, Error::<T>::EventKeyTooLong);
-
-            let event = FSEvent {
-                creationtime: {
-                    let mut arr = [0u8; 64];
-                    arr[..creation_time.len()].copy_from_slice(&creation_time);
-                    arr
-                },
-                filepath: {
-                    let mut arr = [0u8; 256];
-                    arr[..file_path.len()].copy_from_slice(&file_path);
-                    arr
-                },
-                eventkey: {
-                    let mut arr = [0u8; 128];
-                    arr[..event_key.len()].copy_from_slice(&event_key);
-                    arr
-                },
+            let creationtime: BoundedVec<u8, T::MaxCreationTimeLen> =
+                creation_time.try_into().map_err(|_| Error::<T>::CreationTimeTooLong)?;
+            let filepath: BoundedVec<u8, T::MaxFilePathLen> =
+                file_path.try_into().map_err(|_| Error::<T>::FilePathTooLong)?;
+            let eventkey: BoundedVec<u8, T::MaxEventKeyLen> =
+                event_key.try_into().map_err(|_| Error::<T>::EventKeyTooLong)?;
+
+            ensure!(!chunk_hashes.is_empty(), Error::<T>::EmptyChunkList);
+            ensure!(chunk_hashes.len() as u32 <= T::MaxChunks::get(), Error::<T>::TooManyChunks);
+
+            // `event_key` is caller-supplied and not namespaced to an account, so
+            // without this check any account could re-disassemble an existing
+            // `eventkey` to overwrite its manifest and reassign its ownership.
+            if let Some(owner) = <ManifestOwners<T>>::get(&eventkey) {
+                ensure!(owner == sender, Error::<T>::NotFileOwner);
+            }
+
+            let manifest = Manifest {
+                root: Self::merkle_root(&chunk_hashes),
+                chunk_count: chunk_hashes.len() as u32,
             };
+            <Manifests<T>>::insert(&eventkey, manifest);
+            <ManifestOwners<T>>::insert(&eventkey, &sender);
 
-            <DisReAssembly<T>>::insert(&sender, &event);
+            let event = <DisReAssembly<T>>::try_mutate(&sender, |history| -> Result<FSEvent<T>, DispatchError> {
+                let event = FSEvent {
+                    index: history.len() as u32,
+                    creationtime,
+                    filepath,
+                    eventkey,
+                };
+                history.try_push(event.clone()).map_err(|_| Error::<T>::HistoryFull)?;
+                Ok(event)
+            })?;
 
-            Self::deposit_event(Event::<T>::FileDisassembled { who: sender.clone(), event: event.clone() });
+            Self::deposit_event(Event::<T>::FileDisassembled { who: sender, index: event.index, event });
 
             Ok(())
         }
@@ -91,36 +234,167 @@ pub mod pallet_pink_scorpion {
             creation_time: Vec<u8>,
             file_path: Vec<u8>,
             event_key: Vec<u8>,
+            proof: ReassemblyProof,
         ) -> DispatchResult {
             let sender = ensure_signed(origin)?;
 
-            ensure!(creation_time.len() <= 64, Error::<T>::CreationTimeTooLong);
-            ensure!(file_path.len() <= 256, Error::<T>::FilePathTooLong);
-            ensure!(event_key.len() <= 128, Error::<T>::EventKeyTooLong);
-
-            let event = FSEvent {
-                creationtime: {
-                    let mut arr = [0u8; 64];
-                    arr[..creation_time.len()].copy_from_slice(&creation_time);
-                    arr
-                },
-                filepath: {
-                    let mut arr = [0u8; 256];
-                    arr[..file_path.len()].copy_from_slice(&file_path);
-                    arr
-                },
-                eventkey: {
-                    let mut arr = [0u8; 128];
-                    arr[..event_key.len()].copy_from_slice(&event_key);
-                    arr
-                },
+            let creationtime: BoundedVec<u8, T::MaxCreationTimeLen> =
+                creation_time.try_into().map_err(|_| Error::<T>::CreationTimeTooLong)?;
+            let filepath: BoundedVec<u8, T::MaxFilePathLen> =
+                file_path.try_into().map_err(|_| Error::<T>::FilePathTooLong)?;
+            let eventkey: BoundedVec<u8, T::MaxEventKeyLen> =
+                event_key.try_into().map_err(|_| Error::<T>::EventKeyTooLong)?;
+
+            let manifest = <Manifests<T>>::get(&eventkey).ok_or(Error::<T>::UnknownManifest)?;
+            ensure!(<AccessGrants<T>>::contains_key(&eventkey, &sender), Error::<T>::AccessDenied);
+
+            let computed_root = match proof {
+                ReassemblyProof::Leaves(chunk_hashes) => {
+                    ensure!(!chunk_hashes.is_empty(), Error::<T>::EmptyChunkList);
+                    ensure!(chunk_hashes.len() as u32 <= T::MaxChunks::get(), Error::<T>::TooManyChunks);
+                    ensure!(chunk_hashes.len() as u32 == manifest.chunk_count, Error::<T>::ChunkCountMismatch);
+                    Self::merkle_root(&chunk_hashes)
+                }
+                ReassemblyProof::Inclusion { chunk_hash, proof } => {
+                    ensure!(proof.len() as u32 <= T::MaxProofLen::get(), Error::<T>::ProofTooLong);
+                    Self::fold_proof(chunk_hash, &proof)
+                }
             };
 
-            <DisReAssembly<T>>::insert(&sender, &event);
+            // `#[pallet::call]` wraps every dispatchable in a storage transaction that
+            // is rolled back on `Err`, which would take the deposited event with it.
+            // A mismatch is therefore recorded as a successful call that reports the
+            // outcome via `ReassemblyMismatch`, rather than as a dispatch error.
+            if computed_root != manifest.root {
+                Self::deposit_event(Event::<T>::ReassemblyMismatch { who: sender, event_key: eventkey });
+                return Ok(());
+            }
+
+            let event = <DisReAssembly<T>>::try_mutate(&sender, |history| -> Result<FSEvent<T>, DispatchError> {
+                let event = FSEvent {
+                    index: history.len() as u32,
+                    creationtime,
+                    filepath,
+                    eventkey,
+                };
+                history.try_push(event.clone()).map_err(|_| Error::<T>::HistoryFull)?;
+                Ok(event)
+            })?;
+
+            Self::deposit_event(Event::<T>::FileReassembled { who: sender, index: event.index, event });
+
+            Ok(())
+        }
+
+        /// Authorise `recipient` to reassemble the file at `event_key` by submitting
+        /// its AES content key, RSA-wrapped for `recipient`'s public key. Only the
+        /// account that disassembled the file may do this.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn grant_access(
+            origin: OriginFor<T>,
+            event_key: Vec<u8>,
+            recipient: T::AccountId,
+            wrapped_key: Vec<u8>,
+            iv: Vec<u8>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let eventkey: BoundedVec<u8, T::MaxEventKeyLen> =
+                event_key.try_into().map_err(|_| Error::<T>::EventKeyTooLong)?;
+            ensure!(
+                <ManifestOwners<T>>::get(&eventkey) == Some(sender),
+                Error::<T>::NotFileOwner
+            );
+
+            let wrapped_key: BoundedVec<u8, T::MaxWrappedKeyLen> =
+                wrapped_key.try_into().map_err(|_| Error::<T>::WrappedKeyTooLong)?;
+            let iv: BoundedVec<u8, T::MaxIvLen> = iv.try_into().map_err(|_| Error::<T>::IvTooLong)?;
+
+            <AccessGrants<T>>::insert(&eventkey, &recipient, WrappedKey { wrapped_key, iv });
 
-            Self::deposit_event(Event::<T>::FileReassembled { who: sender.clone(), event: event.clone() });
+            Self::deposit_event(Event::<T>::AccessGranted { event_key: eventkey, recipient });
 
             Ok(())
         }
+
+        /// Revoke a previously granted recipient's ability to reassemble the file at
+        /// `event_key`. Only the account that disassembled the file may do this.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn revoke_access(
+            origin: OriginFor<T>,
+            event_key: Vec<u8>,
+            recipient: T::AccountId,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let eventkey: BoundedVec<u8, T::MaxEventKeyLen> =
+                event_key.try_into().map_err(|_| Error::<T>::EventKeyTooLong)?;
+            ensure!(
+                <ManifestOwners<T>>::get(&eventkey) == Some(sender),
+                Error::<T>::NotFileOwner
+            );
+
+            ensure!(
+                <AccessGrants<T>>::take(&eventkey, &recipient).is_some(),
+                Error::<T>::UnknownGrant
+            );
+
+            Self::deposit_event(Event::<T>::AccessRevoked { event_key: eventkey, recipient });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Hashes a leaf as `blake2_256(0x00 || chunk_hash)`.
+        fn hash_leaf(chunk_hash: &[u8; 32]) -> [u8; 32] {
+            let mut preimage = [0u8; 33];
+            preimage[0] = 0x00;
+            preimage[1..].copy_from_slice(chunk_hash);
+            blake2_256(&preimage)
+        }
+
+        /// Hashes an internal node as `blake2_256(0x01 || left || right)`.
+        fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+            let mut preimage = [0u8; 65];
+            preimage[0] = 0x01;
+            preimage[1..33].copy_from_slice(left);
+            preimage[33..].copy_from_slice(right);
+            blake2_256(&preimage)
+        }
+
+        /// Folds a list of per-chunk hashes into a single Merkle root, duplicating
+        /// the last node of a level when its count is odd.
+        fn merkle_root(chunk_hashes: &[[u8; 32]]) -> [u8; 32] {
+            let mut level: Vec<[u8; 32]> = chunk_hashes.iter().map(Self::hash_leaf).collect();
+
+            while level.len() > 1 {
+                let mut next_level = Vec::with_capacity(level.len().div_ceil(2));
+                for pair in level.chunks(2) {
+                    let left = pair[0];
+                    let right = pair.get(1).copied().unwrap_or(left);
+                    next_level.push(Self::hash_node(&left, &right));
+                }
+                level = next_level;
+            }
+
+            level[0]
+        }
+
+        /// Folds a single chunk hash up through a Merkle inclusion proof to the root
+        /// it implies.
+        fn fold_proof(chunk_hash: [u8; 32], proof: &[(bool, [u8; 32])]) -> [u8; 32] {
+            let mut acc = Self::hash_leaf(&chunk_hash);
+            for (sibling_is_left, sibling) in proof {
+                acc = if *sibling_is_left {
+                    Self::hash_node(sibling, &acc)
+                } else {
+                    Self::hash_node(&acc, sibling)
+                };
+            }
+            acc
+        }
     }
-}
\ No newline at end of file
+}
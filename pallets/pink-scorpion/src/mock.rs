@@ -0,0 +1,48 @@
+use frame_support::{derive_impl, parameter_types};
+use sp_runtime::BuildStorage;
+
+use crate as pallet_pink_scorpion;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        PinkScorpion: pallet_pink_scorpion,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+parameter_types! {
+    pub const MaxCreationTimeLen: u32 = 64;
+    pub const MaxFilePathLen: u32 = 256;
+    pub const MaxEventKeyLen: u32 = 128;
+    pub const MaxHistory: u32 = 16;
+    pub const MaxChunks: u32 = 8;
+    pub const MaxProofLen: u32 = 8;
+    pub const MaxWrappedKeyLen: u32 = 512;
+    pub const MaxIvLen: u32 = 16;
+}
+
+impl pallet_pink_scorpion::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type MaxCreationTimeLen = MaxCreationTimeLen;
+    type MaxFilePathLen = MaxFilePathLen;
+    type MaxEventKeyLen = MaxEventKeyLen;
+    type MaxHistory = MaxHistory;
+    type MaxChunks = MaxChunks;
+    type MaxProofLen = MaxProofLen;
+    type MaxWrappedKeyLen = MaxWrappedKeyLen;
+    type MaxIvLen = MaxIvLen;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap()
+        .into()
+}
@@ -0,0 +1,520 @@
+use frame_support::{assert_noop, assert_ok, traits::Get, BoundedVec};
+use sp_io::hashing::blake2_256;
+
+use crate::{mock::*, Error, Event};
+
+fn chunk_hashes(n: u8) -> Vec<[u8; 32]> {
+    (0..n).map(|i| [i; 32]).collect()
+}
+
+fn event_key(bytes: &[u8]) -> BoundedVec<u8, MaxEventKeyLen> {
+    bytes.to_vec().try_into().unwrap()
+}
+
+/// Mirrors `Pallet::hash_leaf`: `blake2_256(0x00 || chunk_hash)`.
+fn hash_leaf(chunk_hash: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = [0u8; 33];
+    preimage[0] = 0x00;
+    preimage[1..].copy_from_slice(chunk_hash);
+    blake2_256(&preimage)
+}
+
+/// Mirrors `Pallet::hash_node`: `blake2_256(0x01 || left || right)`.
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = [0u8; 65];
+    preimage[0] = 0x01;
+    preimage[1..33].copy_from_slice(left);
+    preimage[33..].copy_from_slice(right);
+    blake2_256(&preimage)
+}
+
+/// Builds every level of the Merkle tree over `chunk_hashes`, duplicating the
+/// last node of an odd level, mirroring `Pallet::merkle_root`.
+fn merkle_levels(chunk_hashes: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![chunk_hashes.iter().map(hash_leaf).collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels.last().unwrap();
+        let mut next = Vec::with_capacity(level.len().div_ceil(2));
+        for pair in level.chunks(2) {
+            let left = pair[0];
+            let right = pair.get(1).copied().unwrap_or(left);
+            next.push(hash_node(&left, &right));
+        }
+        levels.push(next);
+    }
+    levels
+}
+
+/// Builds the Merkle inclusion proof for the leaf at `index`, in the
+/// `(sibling_is_left, sibling_hash)` form `fold_proof` expects.
+fn inclusion_proof(chunk_hashes: &[[u8; 32]], index: usize) -> Vec<(bool, [u8; 32])> {
+    let levels = merkle_levels(chunk_hashes);
+    let mut proof = Vec::new();
+    let mut idx = index;
+    for level in &levels[..levels.len() - 1] {
+        let sibling_index = idx ^ 1;
+        let sibling = level.get(sibling_index).copied().unwrap_or(level[idx]);
+        proof.push((idx % 2 == 1, sibling));
+        idx /= 2;
+    }
+    proof
+}
+
+#[test]
+fn another_account_cannot_hijack_an_existing_event_key() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"shared-key".to_vec(),
+            chunk_hashes(2),
+        ));
+
+        assert_noop!(
+            PinkScorpion::disassembled(
+                RuntimeOrigin::signed(2),
+                b"2024-01-02".to_vec(),
+                b"/home/mallory/file".to_vec(),
+                b"shared-key".to_vec(),
+                chunk_hashes(3),
+            ),
+            Error::<Test>::NotFileOwner
+        );
+
+        // Alice is still the recorded owner; Mallory's call didn't reassign it.
+        assert_eq!(PinkScorpion::manifest_owner(event_key(b"shared-key")), Some(1));
+    });
+}
+
+#[test]
+fn the_owner_may_re_disassemble_their_own_event_key() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"my-key".to_vec(),
+            chunk_hashes(2),
+        ));
+
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-02".to_vec(),
+            b"/home/alice/file-v2".to_vec(),
+            b"my-key".to_vec(),
+            chunk_hashes(4),
+        ));
+
+        assert_eq!(PinkScorpion::manifest_owner(event_key(b"my-key")), Some(1));
+        assert_eq!(PinkScorpion::manifest(event_key(b"my-key")).unwrap().chunk_count, 4);
+    });
+}
+
+#[test]
+fn reassembly_requires_an_access_grant() {
+    new_test_ext().execute_with(|| {
+        let chunks = chunk_hashes(2);
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"my-file".to_vec(),
+            chunks.clone(),
+        ));
+
+        assert_noop!(
+            PinkScorpion::reassembled(
+                RuntimeOrigin::signed(2),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"my-file".to_vec(),
+                crate::ReassemblyProof::Leaves(chunks.clone()),
+            ),
+            Error::<Test>::AccessDenied
+        );
+
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"my-file".to_vec(),
+            2,
+            b"wrapped-key-for-bob".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        assert_ok!(PinkScorpion::reassembled(
+            RuntimeOrigin::signed(2),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"my-file".to_vec(),
+            crate::ReassemblyProof::Leaves(chunks.clone()),
+        ));
+
+        assert_ok!(PinkScorpion::revoke_access(
+            RuntimeOrigin::signed(1),
+            b"my-file".to_vec(),
+            2,
+        ));
+
+        assert_noop!(
+            PinkScorpion::reassembled(
+                RuntimeOrigin::signed(2),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"my-file".to_vec(),
+                crate::ReassemblyProof::Leaves(chunks),
+            ),
+            Error::<Test>::AccessDenied
+        );
+    });
+}
+
+#[test]
+fn only_the_owner_can_grant_or_revoke_access() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"my-file".to_vec(),
+            chunk_hashes(2),
+        ));
+
+        assert_noop!(
+            PinkScorpion::grant_access(
+                RuntimeOrigin::signed(2),
+                b"my-file".to_vec(),
+                3,
+                b"wrapped".to_vec(),
+                b"iv".to_vec(),
+            ),
+            Error::<Test>::NotFileOwner
+        );
+
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"my-file".to_vec(),
+            3,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        assert_noop!(
+            PinkScorpion::revoke_access(RuntimeOrigin::signed(2), b"my-file".to_vec(), 3),
+            Error::<Test>::NotFileOwner
+        );
+    });
+}
+
+#[test]
+fn reassembles_from_the_full_leaf_list() {
+    new_test_ext().execute_with(|| {
+        let chunks = chunk_hashes(4);
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"leaves-file".to_vec(),
+            chunks.clone(),
+        ));
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"leaves-file".to_vec(),
+            1,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        assert_ok!(PinkScorpion::reassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"leaves-file".to_vec(),
+            crate::ReassemblyProof::Leaves(chunks),
+        ));
+    });
+}
+
+#[test]
+fn reassembles_from_an_inclusion_proof_with_an_odd_chunk_count() {
+    new_test_ext().execute_with(|| {
+        // Three chunks is an odd count, exercising the duplicate-last-node branch.
+        let chunks = chunk_hashes(3);
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"odd-file".to_vec(),
+            chunks.clone(),
+        ));
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"odd-file".to_vec(),
+            1,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        let proof = inclusion_proof(&chunks, 2);
+
+        assert_ok!(PinkScorpion::reassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"odd-file".to_vec(),
+            crate::ReassemblyProof::Inclusion { chunk_hash: chunks[2], proof },
+        ));
+    });
+}
+
+#[test]
+fn a_tampered_leaf_list_is_rejected_as_a_mismatch() {
+    new_test_ext().execute_with(|| {
+        // Events are not recorded at block 0.
+        System::set_block_number(1);
+        let chunks = chunk_hashes(4);
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"tampered-file".to_vec(),
+            chunks.clone(),
+        ));
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"tampered-file".to_vec(),
+            1,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        let mut tampered = chunks;
+        tampered[1] = [0xFF; 32];
+
+        // A mismatch is reported as a successful call (see the comment on this branch
+        // in `reassembled`): returning `Err` here would roll back the very event that
+        // is supposed to make the mismatch provable on-chain.
+        assert_ok!(PinkScorpion::reassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"tampered-file".to_vec(),
+            crate::ReassemblyProof::Leaves(tampered),
+        ));
+
+        System::assert_has_event(
+            Event::<Test>::ReassemblyMismatch { who: 1, event_key: event_key(b"tampered-file") }.into(),
+        );
+
+        // A mismatch is not a successful reassembly, so it isn't logged to history.
+        assert_eq!(PinkScorpion::history(1).len(), 1);
+    });
+}
+
+#[test]
+fn disassembled_errors_once_the_account_history_is_full() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..MaxHistory::get() {
+            assert_ok!(PinkScorpion::disassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"my-key".to_vec(),
+                chunk_hashes(1),
+            ));
+        }
+        assert_eq!(PinkScorpion::history(1).len() as u32, MaxHistory::get());
+
+        assert_noop!(
+            PinkScorpion::disassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"my-key".to_vec(),
+                chunk_hashes(1),
+            ),
+            Error::<Test>::HistoryFull
+        );
+    });
+}
+
+#[test]
+fn disassembled_rejects_an_empty_chunk_list() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PinkScorpion::disassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"empty-file".to_vec(),
+                Vec::new(),
+            ),
+            Error::<Test>::EmptyChunkList
+        );
+    });
+}
+
+#[test]
+fn disassembled_rejects_more_chunks_than_max_chunks() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PinkScorpion::disassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"too-many-file".to_vec(),
+                chunk_hashes(MaxChunks::get() as u8 + 1),
+            ),
+            Error::<Test>::TooManyChunks
+        );
+    });
+}
+
+#[test]
+fn reassembled_requires_a_known_manifest() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PinkScorpion::reassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"never-disassembled".to_vec(),
+                crate::ReassemblyProof::Leaves(chunk_hashes(1)),
+            ),
+            Error::<Test>::UnknownManifest
+        );
+    });
+}
+
+#[test]
+fn reassembled_rejects_a_leaf_count_that_does_not_match_the_manifest() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"count-mismatch-file".to_vec(),
+            chunk_hashes(4),
+        ));
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"count-mismatch-file".to_vec(),
+            1,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        assert_noop!(
+            PinkScorpion::reassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"count-mismatch-file".to_vec(),
+                crate::ReassemblyProof::Leaves(chunk_hashes(3)),
+            ),
+            Error::<Test>::ChunkCountMismatch
+        );
+    });
+}
+
+#[test]
+fn reassembled_rejects_an_inclusion_proof_longer_than_max_proof_len() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"long-proof-file".to_vec(),
+            chunk_hashes(2),
+        ));
+        assert_ok!(PinkScorpion::grant_access(
+            RuntimeOrigin::signed(1),
+            b"long-proof-file".to_vec(),
+            1,
+            b"wrapped".to_vec(),
+            b"iv".to_vec(),
+        ));
+
+        let too_long_proof = vec![(false, [0u8; 32]); MaxProofLen::get() as usize + 1];
+
+        assert_noop!(
+            PinkScorpion::reassembled(
+                RuntimeOrigin::signed(1),
+                b"2024-01-01".to_vec(),
+                b"/home/alice/file".to_vec(),
+                b"long-proof-file".to_vec(),
+                crate::ReassemblyProof::Inclusion {
+                    chunk_hash: [0u8; 32],
+                    proof: too_long_proof,
+                },
+            ),
+            Error::<Test>::ProofTooLong
+        );
+    });
+}
+
+#[test]
+fn grant_access_rejects_a_wrapped_key_that_is_too_long() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"wrapped-key-file".to_vec(),
+            chunk_hashes(1),
+        ));
+
+        assert_noop!(
+            PinkScorpion::grant_access(
+                RuntimeOrigin::signed(1),
+                b"wrapped-key-file".to_vec(),
+                2,
+                vec![0u8; MaxWrappedKeyLen::get() as usize + 1],
+                b"iv".to_vec(),
+            ),
+            Error::<Test>::WrappedKeyTooLong
+        );
+    });
+}
+
+#[test]
+fn grant_access_rejects_an_iv_that_is_too_long() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"iv-file".to_vec(),
+            chunk_hashes(1),
+        ));
+
+        assert_noop!(
+            PinkScorpion::grant_access(
+                RuntimeOrigin::signed(1),
+                b"iv-file".to_vec(),
+                2,
+                b"wrapped".to_vec(),
+                vec![0u8; MaxIvLen::get() as usize + 1],
+            ),
+            Error::<Test>::IvTooLong
+        );
+    });
+}
+
+#[test]
+fn revoke_access_requires_an_existing_grant() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PinkScorpion::disassembled(
+            RuntimeOrigin::signed(1),
+            b"2024-01-01".to_vec(),
+            b"/home/alice/file".to_vec(),
+            b"ungranted-file".to_vec(),
+            chunk_hashes(1),
+        ));
+
+        assert_noop!(
+            PinkScorpion::revoke_access(RuntimeOrigin::signed(1), b"ungranted-file".to_vec(), 2),
+            Error::<Test>::UnknownGrant
+        );
+    });
+}